@@ -0,0 +1,124 @@
+//! Generate the `Instruction` enum, its decoder, and the opcode/arity/mnemonic
+//! lookup tables from `instructions.in`. Emitting these from a single spec keeps
+//! the decoder, disassembler, and assembler from drifting out of sync: adding or
+//! tweaking an opcode is a one-line edit to the spec.
+
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+fn main() {
+    println!("cargo:rerun-if-changed=instructions.in");
+
+    let spec = fs::read_to_string("instructions.in").expect("read instructions.in");
+
+    // (opcode, mnemonic, variant, arity)
+    let ops: Vec<(u16, String, String, usize)> = spec
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|line| {
+            let mut fields = line.split_whitespace();
+            let opcode: u16 = fields.next().unwrap().parse().unwrap();
+            let mnemonic = fields.next().unwrap().to_string();
+            let arity: usize = fields.next().unwrap().parse().unwrap();
+            let variant = capitalize(&mnemonic);
+            (opcode, mnemonic, variant, arity)
+        })
+        .collect();
+
+    let mut out = String::new();
+
+    // Enum definition.
+    out.push_str("#[derive(Debug)]\nenum Instruction {\n");
+    for (_, _, variant, arity) in &ops {
+        match arity {
+            0 => writeln!(out, "    {},", variant).unwrap(),
+            n => {
+                let fields: Vec<String> = (1..=*n).map(|i| format!("op{}: Address", i)).collect();
+                writeln!(out, "    {} {{ {} }},", variant, fields.join(", ")).unwrap();
+            }
+        }
+    }
+    out.push_str("}\n\n");
+
+    // Decoder plus mnemonic lookup.
+    out.push_str("impl Instruction {\n");
+    out.push_str("    fn decoder(data: &[u16], index: usize) -> Result<(Instruction, usize)> {\n");
+    out.push_str("        let opcode = data[index];\n");
+    out.push_str("        let mut idx = index + 1;\n");
+    out.push_str("        let instruction = match opcode {\n");
+    for (opcode, _, variant, arity) in &ops {
+        if *arity == 0 {
+            writeln!(out, "            {} => Instruction::{},", opcode, variant).unwrap();
+        } else {
+            writeln!(out, "            {} => {{", opcode).unwrap();
+            for i in 1..=*arity {
+                // Code and data interleave, so an opcode near the end of the
+                // image may claim operands that do not exist. Bail instead of
+                // indexing past the buffer so the disassembler falls back to
+                // `.data` rather than panicking.
+                out.push_str(
+                    "                if idx >= data.len() {\n                    return Err(VmError::InvalidOpcode { opcode, index: idx });\n                }\n",
+                );
+                writeln!(out, "                let op{} = Address::from(data[idx])?;", i).unwrap();
+                out.push_str("                idx += 1;\n");
+            }
+            let fields: Vec<String> = (1..=*arity).map(|i| format!("op{}", i)).collect();
+            writeln!(
+                out,
+                "                Instruction::{} {{ {} }}",
+                variant,
+                fields.join(", ")
+            )
+            .unwrap();
+            out.push_str("            }\n");
+        }
+    }
+    out.push_str("            _ => return Err(VmError::InvalidOpcode { opcode, index: idx }),\n");
+    out.push_str("        };\n");
+    out.push_str("        #[cfg(feature = \"std\")]\n");
+    out.push_str("        trace!(\n");
+    out.push_str("            \"Opcode {}, instruction {:?}, length {}\",\n");
+    out.push_str("            opcode,\n            instruction,\n            idx - index\n        );\n");
+    out.push_str("        Ok((instruction, idx - index))\n");
+    out.push_str("    }\n\n");
+
+    out.push_str("    fn mnemonic(&self) -> &'static str {\n        match self {\n");
+    for (_, mnemonic, variant, arity) in &ops {
+        let pat = if *arity == 0 {
+            format!("Instruction::{}", variant)
+        } else {
+            format!("Instruction::{} {{ .. }}", variant)
+        };
+        writeln!(out, "            {} => \"{}\",", pat, mnemonic).unwrap();
+    }
+    out.push_str("        }\n    }\n}\n\n");
+
+    // Shared lookup tables for the assembler.
+    out.push_str("/// Operand count for an opcode, or `None` if the opcode is unknown.\n");
+    out.push_str("pub fn arity(opcode: u16) -> Option<usize> {\n    match opcode {\n");
+    for (opcode, _, _, arity) in &ops {
+        writeln!(out, "        {} => Some({}),", opcode, arity).unwrap();
+    }
+    out.push_str("        _ => None,\n    }\n}\n\n");
+
+    out.push_str("/// Opcode for a mnemonic, or `None` if the mnemonic is unknown.\n");
+    out.push_str("pub fn opcode(mnemonic: &str) -> Option<u16> {\n    match mnemonic {\n");
+    for (opcode, mnemonic, _, _) in &ops {
+        writeln!(out, "        \"{}\" => Some({}),", mnemonic, opcode).unwrap();
+    }
+    out.push_str("        _ => None,\n    }\n}\n");
+
+    let dest = Path::new(&env::var("OUT_DIR").unwrap()).join("instructions.rs");
+    fs::write(dest, out).expect("write generated instructions");
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+        None => String::new(),
+    }
+}
@@ -0,0 +1,236 @@
+extern crate anyhow;
+
+use anyhow::{bail, Result};
+#[allow(unused_imports)]
+use log::{debug, error, info, trace, warn};
+
+use std::collections::HashMap;
+
+use synacor::virt;
+
+/// Assemble Synacor source text into the little-endian `u16` image that `main`
+/// loads. One mnemonic (or a `.data` directive) per line; operands are decimal
+/// literals, `r0..r7` register names, char literals, or symbolic labels. Labels
+/// are resolved in two passes: pass one fixes each line's address using the
+/// per-opcode operand counts, pass two emits words and substitutes addresses.
+pub fn assemble(src: &str) -> Result<Vec<u16>> {
+    let mut symbols: HashMap<String, u16> = HashMap::new();
+
+    // Pass one: assign an address to every label.
+    let mut addr: u16 = 0;
+    for line in src.lines() {
+        let mut tokens = tokenize(line)?;
+
+        while let Some(label) = tokens.first().and_then(|t| t.strip_suffix(':')) {
+            symbols.insert(label.to_string(), addr);
+            tokens.remove(0);
+        }
+
+        if tokens.is_empty() {
+            continue;
+        }
+
+        addr += line_size(&tokens)?;
+    }
+
+    // Pass two: emit words, resolving label references.
+    let mut image: Vec<u16> = Vec::new();
+    for line in src.lines() {
+        let mut tokens = tokenize(line)?;
+
+        while tokens.first().is_some_and(|t| t.ends_with(':')) {
+            tokens.remove(0);
+        }
+
+        if tokens.is_empty() {
+            continue;
+        }
+
+        if tokens[0] == ".data" {
+            for operand in &tokens[1..] {
+                emit_operand(&mut image, operand, &symbols)?;
+            }
+            continue;
+        }
+
+        let opcode = match virt::opcode(&tokens[0]) {
+            Some(opcode) => opcode,
+            None => bail!("unknown mnemonic: {}", tokens[0]),
+        };
+        let arity = virt::arity(opcode).unwrap();
+
+        if tokens.len() - 1 != arity {
+            bail!(
+                "{} takes {} operand(s), got {}",
+                tokens[0],
+                arity,
+                tokens.len() - 1
+            );
+        }
+
+        image.push(opcode);
+        for operand in &tokens[1..] {
+            image.push(parse_operand(operand, &symbols)?);
+        }
+    }
+
+    Ok(image)
+}
+
+// Number of words a non-empty token line emits.
+fn line_size(tokens: &[String]) -> Result<u16> {
+    if tokens[0] == ".data" {
+        let mut size: u16 = 0;
+        for operand in &tokens[1..] {
+            size += operand_words(operand);
+        }
+        return Ok(size);
+    }
+
+    match virt::opcode(&tokens[0]) {
+        Some(opcode) => Ok(1 + virt::arity(opcode).unwrap() as u16),
+        None => bail!("unknown mnemonic: {}", tokens[0]),
+    }
+}
+
+// How many words a `.data` operand emits: one per byte for a string literal,
+// otherwise a single word.
+fn operand_words(operand: &str) -> u16 {
+    if operand.starts_with('"') {
+        (operand.len() - 2) as u16
+    } else {
+        1
+    }
+}
+
+// Emit a `.data` operand, expanding string literals to one word per byte.
+fn emit_operand(image: &mut Vec<u16>, operand: &str, symbols: &HashMap<String, u16>) -> Result<()> {
+    if let Some(inner) = operand.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        for byte in inner.bytes() {
+            image.push(byte as u16);
+        }
+        Ok(())
+    } else {
+        image.push(parse_operand(operand, symbols)?);
+        Ok(())
+    }
+}
+
+// Resolve a single operand token to its word value.
+fn parse_operand(operand: &str, symbols: &HashMap<String, u16>) -> Result<u16> {
+    // Register: r0..r7 -> 32768 + index.
+    if let Some(rest) = operand.strip_prefix('r') {
+        if let Ok(idx) = rest.parse::<u16>() {
+            if idx < 8 {
+                return Ok(32768 + idx);
+            }
+        }
+    }
+
+    // Char literal: 'A' or '\n'.
+    if let Some(inner) = operand.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')) {
+        let value = match inner {
+            "\\n" => 10,
+            "\\t" => 9,
+            "\\0" => 0,
+            s if s.len() == 1 => s.as_bytes()[0] as u16,
+            _ => bail!("invalid char literal: {}", operand),
+        };
+        return Ok(value);
+    }
+
+    // Decimal literal.
+    if let Ok(value) = operand.parse::<u16>() {
+        return Ok(value);
+    }
+
+    // Otherwise a label reference.
+    match symbols.get(operand) {
+        Some(addr) => Ok(*addr),
+        None => bail!("unknown label: {}", operand),
+    }
+}
+
+// Split a line into tokens, honoring quoted strings, `'c'` char literals, and
+// `;` comments. Operands may be separated by commas as well as whitespace, so
+// disassembler output (`add r0, r1, r2`) feeds straight back in. Quoted strings
+// and char literals keep their surrounding quotes so later stages can tell them
+// apart from a bare label, and the quotes shield an embedded space or `;` from
+// the separator/comment handling.
+fn tokenize(line: &str) -> Result<Vec<String>> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            ';' => break,
+            c if c.is_whitespace() || c == ',' => {
+                chars.next();
+            }
+            '"' => {
+                let mut token = String::from('"');
+                chars.next();
+                loop {
+                    match chars.next() {
+                        Some('"') => {
+                            token.push('"');
+                            break;
+                        }
+                        Some(c) => token.push(c),
+                        None => bail!("unterminated string literal"),
+                    }
+                }
+                tokens.push(token);
+            }
+            '\'' => {
+                // A char literal is a single char or a two-char escape such as
+                // `\n`, wrapped in quotes; consume it whole so a quoted space or
+                // `;` is not mistaken for a separator or comment.
+                let mut token = String::from('\'');
+                chars.next();
+                match chars.next() {
+                    Some('\\') => {
+                        token.push('\\');
+                        match chars.next() {
+                            Some(c) => token.push(c),
+                            None => bail!("unterminated char literal"),
+                        }
+                    }
+                    Some(c) => token.push(c),
+                    None => bail!("unterminated char literal"),
+                }
+                match chars.next() {
+                    Some('\'') => token.push('\''),
+                    _ => bail!("unterminated char literal"),
+                }
+                tokens.push(token);
+            }
+            _ => {
+                let mut token = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == ';' || c == ',' {
+                        break;
+                    }
+                    token.push(c);
+                    chars.next();
+                }
+                tokens.push(token);
+            }
+        }
+    }
+
+    // Accept disassembler output directly: the listing prefixes each line with a
+    // four-digit hex address column. Drop it when a mnemonic or directive
+    // follows.
+    if tokens.len() >= 2 && is_address_column(&tokens[0]) {
+        tokens.remove(0);
+    }
+
+    Ok(tokens)
+}
+
+// A disassembler address column: exactly four hexadecimal digits. No mnemonic
+// or label matches this, so stripping it cannot shadow real source.
+fn is_address_column(token: &str) -> bool {
+    token.len() == 4 && token.bytes().all(|b| b.is_ascii_hexdigit())
+}
@@ -0,0 +1,176 @@
+extern crate anyhow;
+
+use anyhow::{bail, Result};
+#[allow(unused_imports)]
+use log::{debug, error, info, trace, warn};
+
+use synacor::virt::{StepResult, VirtualMachine};
+
+/// Interactive debugger wrapped around a [`VirtualMachine`].
+///
+/// Supports PC breakpoints, single-stepping, and inspection or patching of the
+/// registers, memory, and stack. This is what the Synacor teleporter puzzle
+/// needs: set register 7, short-circuit the confirmation routine, and continue.
+pub struct Debugger {
+    breakpoints: Vec<usize>,
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Self {
+            breakpoints: Vec::new(),
+        }
+    }
+
+    /// Run the debugger REPL against `vm`, reading commands from stdin until
+    /// the VM halts or the user quits.
+    pub fn run(&mut self, vm: &mut VirtualMachine) -> Result<()> {
+        loop {
+            eprint!("(dbg) ");
+            use std::io::Write;
+            std::io::stderr().flush()?;
+
+            let mut line = String::new();
+            if std::io::stdin().read_line(&mut line)? == 0 {
+                break;
+            }
+
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            if tokens.is_empty() {
+                continue;
+            }
+
+            match tokens[0] {
+                "break" | "b" => match parse_addr(tokens.get(1)) {
+                    Ok(addr) => {
+                        self.breakpoints.push(addr);
+                        eprintln!("breakpoint set at {:#06x}", addr);
+                    }
+                    Err(e) => eprintln!("error: {}", e),
+                },
+
+                "continue" | "c" => {
+                    if self.cont(vm)? {
+                        break;
+                    }
+                }
+
+                "step" | "s" => match vm.step()? {
+                    StepResult::Halted => {
+                        eprintln!("halted");
+                        break;
+                    }
+                    StepResult::NeedInput => eprintln!("waiting for input"),
+                    StepResult::Continue => eprintln!("pc = {:#06x}", vm.pc()),
+                },
+
+                "regs" | "r" => {
+                    for (idx, val) in vm.registers().iter().enumerate() {
+                        eprintln!("r{} = {:#06x} ({})", idx, val, val);
+                    }
+                }
+
+                "set" => match self.set(vm, &tokens) {
+                    Ok(_) => {}
+                    Err(e) => eprintln!("error: {}", e),
+                },
+
+                "mem" => match self.mem(vm, &tokens) {
+                    Ok(_) => {}
+                    Err(e) => eprintln!("error: {}", e),
+                },
+
+                "poke" => match (parse_addr(tokens.get(1)), parse_addr(tokens.get(2))) {
+                    (Ok(addr), Ok(val)) if addr < vm.memory().len() => {
+                        vm.poke(addr, val as u16);
+                        eprintln!("{:#06x} <- {}", addr, val as u16);
+                    }
+                    (Ok(addr), Ok(_)) => eprintln!("error: address out of range: {:#06x}", addr),
+                    (Err(e), _) | (_, Err(e)) => eprintln!("error: {}", e),
+                },
+
+                "stack" => {
+                    for val in vm.stack().iter().rev() {
+                        eprintln!("{:#06x} ({})", val, val);
+                    }
+                }
+
+                "quit" | "q" => break,
+
+                other => eprintln!("error: unknown command: {}", other),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Step until a breakpoint is hit or the VM halts. Returns `true` if the VM
+    /// halted.
+    fn cont(&self, vm: &mut VirtualMachine) -> Result<bool> {
+        loop {
+            match vm.step()? {
+                StepResult::Halted => {
+                    eprintln!("halted");
+                    return Ok(true);
+                }
+                StepResult::NeedInput => {
+                    eprintln!("waiting for input");
+                    return Ok(false);
+                }
+                StepResult::Continue => {}
+            }
+
+            if self.breakpoints.contains(&vm.pc()) {
+                eprintln!("breakpoint hit at {:#06x}", vm.pc());
+                return Ok(false);
+            }
+        }
+    }
+
+    fn set(&self, vm: &mut VirtualMachine, tokens: &[&str]) -> Result<()> {
+        let idx = parse_reg(tokens.get(1))?;
+        let val = parse_addr(tokens.get(2))? as u16;
+        vm.set_register(idx, val);
+        eprintln!("r{} <- {}", idx, val);
+        Ok(())
+    }
+
+    fn mem(&self, vm: &mut VirtualMachine, tokens: &[&str]) -> Result<()> {
+        let addr = parse_addr(tokens.get(1))?;
+        let count = match tokens.get(2) {
+            Some(_) => parse_addr(tokens.get(2))?,
+            None => 1,
+        };
+
+        let memory = vm.memory();
+        for off in 0..count {
+            let at = addr + off;
+            if at >= memory.len() {
+                break;
+            }
+            eprintln!("{:#06x}: {:#06x} ({})", at, memory[at], memory[at]);
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_addr(token: Option<&&str>) -> Result<usize> {
+    match token {
+        Some(t) => Ok(t.parse::<usize>()?),
+        None => bail!("expected an address"),
+    }
+}
+
+fn parse_reg(token: Option<&&str>) -> Result<usize> {
+    match token {
+        Some(t) if t.starts_with('r') => {
+            let idx = t[1..].parse::<usize>()?;
+            if idx >= 8 {
+                bail!("register out of range (r0..r7): {}", t);
+            }
+            Ok(idx)
+        }
+        _ => bail!("expected a register (r0..r7)"),
+    }
+}
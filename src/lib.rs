@@ -0,0 +1,11 @@
+//! Synacor VM core.
+//!
+//! The crate is `no_std` by default-off: with the `std` feature (on by default)
+//! it links the standard library for the terminal front-end; without it the
+//! `virt` module still compiles against `core` + `alloc`, so the VM can be
+//! embedded in constrained hosts or a WASM sandbox.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod virt;
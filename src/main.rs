@@ -1,27 +1,65 @@
 extern crate anyhow;
 
+mod assembler;
+mod debugger;
 mod utils;
-mod virt;
 
 use anyhow::Result;
 #[allow(unused_imports)]
 use log::{debug, error, info, trace, warn};
+use synacor::virt;
 
 fn main() -> Result<()> {
-    let opts = vec![clap::Arg::with_name("infile")
-        .help("Input filename")
-        .required(true)];
+    let opts = vec![
+        clap::Arg::with_name("disasm")
+            .help("Disassemble the binary and exit")
+            .long("disasm"),
+        clap::Arg::with_name("assemble")
+            .help("Assemble the input .s file to <infile>.bin and exit")
+            .long("assemble"),
+        clap::Arg::with_name("debug")
+            .help("Run under the interactive debugger")
+            .long("debug"),
+        clap::Arg::with_name("infile")
+            .help("Input filename")
+            .required(true),
+    ];
 
     let args = utils::init(Some(opts))?;
 
-    let bytes = std::fs::read(args.value_of("infile").unwrap())?;
+    let infile = args.value_of("infile").unwrap();
+
+    if args.is_present("assemble") {
+        let src = std::fs::read_to_string(infile)?;
+        let image = assembler::assemble(&src)?;
+        let mut bytes = Vec::with_capacity(image.len() * 2);
+        for word in &image {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        let outfile = format!("{}.bin", infile);
+        std::fs::write(&outfile, bytes)?;
+        info!("wrote {} words to {}", image.len(), outfile);
+        return Ok(());
+    }
+
+    let bytes = std::fs::read(infile)?;
     let data: Vec<u16> = bytes
         .chunks_exact(2)
-        .into_iter()
         .map(|b| u16::from_ne_bytes([b[0], b[1]]))
         .collect();
 
+    if args.is_present("disasm") {
+        print!("{}", virt::disassemble(&data));
+        return Ok(());
+    }
+
     let mut vm = virt::VirtualMachine::new(data);
+
+    if args.is_present("debug") {
+        debugger::Debugger::new().run(&mut vm)?;
+        return Ok(());
+    }
+
     match vm.run() {
         Ok(_) => {}
         Err(e) => {
@@ -1,9 +1,163 @@
-extern crate anyhow;
+extern crate alloc;
 
-use anyhow::{bail, Result};
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::convert::TryInto;
+
+#[cfg(feature = "std")]
 #[allow(unused_imports)]
 use log::{debug, error, info, trace, warn};
 
+/// Errors raised by the `no_std` VM core, replacing `anyhow` on the hot path.
+///
+/// The core only needs heap allocation, so execution and decoding return this
+/// plain enum; the `std` layer implements [`std::error::Error`] for it so the
+/// terminal front-end can keep using `anyhow`'s `?`.
+#[derive(Debug)]
+pub enum VmError {
+    /// A word was neither a valid literal nor a register reference.
+    InvalidAddress(u16),
+    /// The decoder hit a word that is not a known opcode.
+    InvalidOpcode { opcode: u16, index: usize },
+    /// An instruction expected a register operand but got a literal.
+    InvalidRegister,
+    /// `pop` ran against an empty stack.
+    StackUnderflow,
+    /// `in` ran out of buffered input.
+    InputExhausted,
+    /// A snapshot file was truncated or otherwise malformed.
+    #[cfg(feature = "std")]
+    BadSnapshot(String),
+    /// An underlying terminal or file I/O error.
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+}
+
+impl core::fmt::Display for VmError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            VmError::InvalidAddress(val) => write!(f, "Invalid address: {}", val),
+            VmError::InvalidOpcode { opcode, index } => {
+                write!(f, "Invalid opcode: {} [{}]", opcode, index)
+            }
+            VmError::InvalidRegister => write!(f, "Invalid register"),
+            VmError::StackUnderflow => write!(f, "Invalid stack pop"),
+            VmError::InputExhausted => write!(f, "input buffer exhausted"),
+            #[cfg(feature = "std")]
+            VmError::BadSnapshot(msg) => write!(f, "{}", msg),
+            #[cfg(feature = "std")]
+            VmError::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for VmError {}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for VmError {
+    fn from(err: std::io::Error) -> Self {
+        VmError::Io(err)
+    }
+}
+
+/// Result type for the VM core.
+pub type Result<T> = core::result::Result<T, VmError>;
+
+#[cfg(feature = "std")]
+const SNAPSHOT_MAGIC: &[u8] = b"SNAP";
+// Version 2 appends the transport's pending input after the stack.
+#[cfg(feature = "std")]
+const SNAPSHOT_VERSION: u8 = 2;
+
+// Append a length-prefixed (u32 count) run of little-endian u16 words.
+#[cfg(feature = "std")]
+fn write_words(buf: &mut Vec<u8>, words: &[u16]) {
+    buf.extend_from_slice(&(words.len() as u32).to_le_bytes());
+    for word in words {
+        buf.extend_from_slice(&word.to_le_bytes());
+    }
+}
+
+// Append a length-prefixed (u32 count) run of raw bytes.
+#[cfg(feature = "std")]
+fn write_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    buf.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    buf.extend_from_slice(bytes);
+}
+
+// A bounds-checked reader over a snapshot buffer. Every read validates the
+// remaining length so a truncated file errors instead of panicking.
+#[cfg(feature = "std")]
+struct Cursor<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+#[cfg(feature = "std")]
+impl<'a> Cursor<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.pos + len > self.buf.len() {
+            return Err(VmError::BadSnapshot(format!(
+                "truncated snapshot: wanted {} bytes at offset {}",
+                len, self.pos
+            )));
+        }
+        let slice = &self.buf[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(slice)
+    }
+
+    fn remaining(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+}
+
+// Read a length-prefixed run of little-endian u16 words written by `write_words`.
+#[cfg(feature = "std")]
+fn read_words(cur: &mut Cursor) -> Result<Vec<u16>> {
+    let count = u32::from_le_bytes(cur.take(4)?.try_into().unwrap()) as usize;
+    // The count comes straight from the file header, so validate it against the
+    // bytes actually left before reserving: a hostile `count` must fail cleanly,
+    // not trigger a huge speculative allocation.
+    if count.saturating_mul(2) > cur.remaining() {
+        return Err(VmError::BadSnapshot(format!(
+            "truncated snapshot: {} words need {} bytes, {} remain",
+            count,
+            count.saturating_mul(2),
+            cur.remaining()
+        )));
+    }
+    let mut words = Vec::with_capacity(count);
+    for _ in 0..count {
+        words.push(u16::from_le_bytes(cur.take(2)?.try_into().unwrap()));
+    }
+    Ok(words)
+}
+
+// Read a length-prefixed (u32 count) run of raw bytes, validating the count
+// against the remaining buffer before reserving.
+#[cfg(feature = "std")]
+fn read_bytes(cur: &mut Cursor) -> Result<Vec<u8>> {
+    let count = u32::from_le_bytes(cur.take(4)?.try_into().unwrap()) as usize;
+    if count > cur.remaining() {
+        return Err(VmError::BadSnapshot(format!(
+            "truncated snapshot: {} bytes wanted, {} remain",
+            count,
+            cur.remaining()
+        )));
+    }
+    Ok(cur.take(count)?.to_vec())
+}
+
 #[derive(Debug)]
 enum Address {
     Literal(u16),
@@ -15,167 +169,266 @@ impl Address {
         let addr = match val {
             0..=32767 => Address::Literal(val),
             32768..=32775 => Address::Register(val - 32768),
-            _ => {
-                bail!("Invalid address: {}", val);
-            }
+            _ => return Err(VmError::InvalidAddress(val)),
         };
 
         Ok(addr)
     }
-}
 
-#[derive(Debug)]
-enum Instruction {
-    Halt,
-    Set {
-        op1: Address,
-        op2: Address,
-    },
-    Push {
-        op1: Address,
-    },
-    Pop {
-        op1: Address,
-    },
-    Eq {
-        op1: Address,
-        op2: Address,
-        op3: Address,
-    },
-    Gt {
-        op1: Address,
-        op2: Address,
-        op3: Address,
-    },
-    Jmp {
-        op1: Address,
-    },
-    Jt {
-        op1: Address,
-        op2: Address,
-    },
-    Jf {
-        op1: Address,
-        op2: Address,
-    },
-    Add {
-        op1: Address,
-        op2: Address,
-        op3: Address,
-    },
-    Mult {
-        op1: Address,
-        op2: Address,
-        op3: Address,
-    },
-    Mod {
-        op1: Address,
-        op2: Address,
-        op3: Address,
-    },
-    And {
-        op1: Address,
-        op2: Address,
-        op3: Address,
-    },
-    Or {
-        op1: Address,
-        op2: Address,
-        op3: Address,
-    },
-    Not {
-        op1: Address,
-        op2: Address,
-    },
-    Rmem {
-        op1: Address,
-        op2: Address,
-    },
-    Wmem {
-        op1: Address,
-        op2: Address,
-    },
-    Call {
-        op1: Address,
-    },
-    Ret,
-    Out {
-        op1: Address,
-    },
-    In {
-        op1: Address,
-    },
-    Noop,
+    fn disasm(&self) -> String {
+        match self {
+            Address::Register(idx) => format!("r{}", idx),
+            Address::Literal(val) => match val {
+                10 => "'\\n'".to_string(),
+                // A backslash stays numeric: the assembler reads `\` inside a
+                // char literal as the start of an escape.
+                32..=91 | 93..=126 => format!("'{}'", *val as u8 as char),
+                _ => format!("{}", val),
+            },
+        }
+    }
 }
 
+// The `Instruction` enum, its decoder, and the opcode/arity/mnemonic lookup
+// tables are generated from `instructions.in` by `build.rs`.
+include!(concat!(env!("OUT_DIR"), "/instructions.rs"));
+
 impl Instruction {
-    fn decoder(data: &Vec<u16>, index: usize) -> Result<(Instruction, usize)> {
-        let mut idx = index;
-
-        let opcode = &data[idx];
-        idx += 1;
-
-        let instruction = match opcode {
-            // One-byte instructions
-            0 => Instruction::Halt,
-            18 => Instruction::Ret,
-            21 => Instruction::Noop,
-            _ => {
-                // Two-byte instructions
-                let op1 = Address::from(data[idx])?;
-                idx += 1;
+    fn operands(&self) -> Vec<&Address> {
+        match self {
+            Instruction::Halt | Instruction::Ret | Instruction::Noop => vec![],
+            Instruction::Push { op1 }
+            | Instruction::Pop { op1 }
+            | Instruction::Jmp { op1 }
+            | Instruction::Call { op1 }
+            | Instruction::Out { op1 }
+            | Instruction::In { op1 } => vec![op1],
+            Instruction::Set { op1, op2 }
+            | Instruction::Jt { op1, op2 }
+            | Instruction::Jf { op1, op2 }
+            | Instruction::Not { op1, op2 }
+            | Instruction::Rmem { op1, op2 }
+            | Instruction::Wmem { op1, op2 } => vec![op1, op2],
+            Instruction::Eq { op1, op2, op3 }
+            | Instruction::Gt { op1, op2, op3 }
+            | Instruction::Add { op1, op2, op3 }
+            | Instruction::Mult { op1, op2, op3 }
+            | Instruction::Mod { op1, op2, op3 }
+            | Instruction::And { op1, op2, op3 }
+            | Instruction::Or { op1, op2, op3 } => vec![op1, op2, op3],
+        }
+    }
+
+    // The literal address an instruction transfers control to, if any. Used by
+    // the disassembler's first pass to collect label targets.
+    fn target(&self) -> Option<u16> {
+        let op = match self {
+            Instruction::Jmp { op1 } | Instruction::Call { op1 } => op1,
+            Instruction::Jt { op2, .. } | Instruction::Jf { op2, .. } => op2,
+            _ => return None,
+        };
 
-                match opcode {
-                    2 => Instruction::Push { op1 },
-                    3 => Instruction::Pop { op1 },
-                    6 => Instruction::Jmp { op1 },
-                    17 => Instruction::Call { op1 },
-                    19 => Instruction::Out { op1 },
-                    20 => Instruction::In { op1 },
-                    _ => {
-                        // Three-byte instructions
-                        let op2 = Address::from(data[idx])?;
-                        idx += 1;
-
-                        match opcode {
-                            1 => Instruction::Set { op1, op2 },
-                            7 => Instruction::Jt { op1, op2 },
-                            8 => Instruction::Jf { op1, op2 },
-                            14 => Instruction::Not { op1, op2 },
-                            15 => Instruction::Rmem { op1, op2 },
-                            16 => Instruction::Wmem { op1, op2 },
-                            _ => {
-                                // Four-byte instructions
-                                let op3 = Address::from(data[idx])?;
-                                idx += 1;
-
-                                match opcode {
-                                    4 => Instruction::Eq { op1, op2, op3 },
-                                    5 => Instruction::Gt { op1, op2, op3 },
-                                    9 => Instruction::Add { op1, op2, op3 },
-                                    10 => Instruction::Mult { op1, op2, op3 },
-                                    11 => Instruction::Mod { op1, op2, op3 },
-                                    12 => Instruction::And { op1, op2, op3 },
-                                    13 => Instruction::Or { op1, op2, op3 },
-                                    _ => {
-                                        bail!("Invalid opcode: {} [{}]", opcode, idx);
-                                    }
-                                }
-                            }
-                        }
-                    }
+        match op {
+            Address::Literal(addr) => Some(*addr),
+            Address::Register(_) => None,
+        }
+    }
+}
+
+/// Disassemble `data` from offset 0 and return the full listing.
+///
+/// Synacor binaries interleave code and data, so an invalid opcode is not an
+/// error: the offending word is emitted as a `.data` directive and decoding
+/// resumes at the next word. A first pass collects every literal jump/call
+/// target so the second pass can anchor `L_xxxx:` labels at those addresses.
+pub fn disassemble(data: &[u16]) -> String {
+    use alloc::collections::BTreeSet;
+
+    let mut labels: BTreeSet<u16> = BTreeSet::new();
+    let mut idx = 0;
+    while idx < data.len() {
+        match Instruction::decoder(data, idx) {
+            Ok((instruction, length)) => {
+                if let Some(target) = instruction.target() {
+                    labels.insert(target);
                 }
+                idx += length;
             }
-        };
+            Err(_) => idx += 1,
+        }
+    }
+
+    let mut out = String::new();
+    let mut idx = 0;
+    while idx < data.len() {
+        if labels.contains(&(idx as u16)) {
+            out.push_str(&format!("L_{:04x}:\n", idx));
+        }
+
+        match Instruction::decoder(data, idx) {
+            Ok((instruction, length)) => {
+                let operands: Vec<String> =
+                    instruction.operands().iter().map(|a| a.disasm()).collect();
+                out.push_str(&format!(
+                    "{:04x}    {} {}\n",
+                    idx,
+                    instruction.mnemonic(),
+                    operands.join(", ")
+                ));
+                idx += length;
+            }
+            Err(_) => {
+                out.push_str(&format!("{:04x}    .data {}\n", idx, data[idx]));
+                idx += 1;
+            }
+        }
+    }
+
+    out
+}
+
+/// Outcome of executing a single instruction via [`VirtualMachine::step`].
+///
+/// Breakpoints are deliberately not represented here: they are a debugger
+/// concept, so the debugger detects them by comparing `pc` after each
+/// [`step`](VirtualMachine::step). `step` only reports the states
+/// the VM itself knows about — running, halted, or stalled for input.
+#[derive(Debug, PartialEq, Eq)]
+pub enum StepResult {
+    /// The VM executed an instruction and is ready to continue.
+    Continue,
+    /// The VM executed a `halt` and should stop.
+    Halted,
+    /// The VM reached an `in` but the transport has no input ready. The program
+    /// counter is left on the `in` so the next `step` retries once input is
+    /// available.
+    NeedInput,
+}
+
+/// Byte-oriented transport the VM talks to instead of touching stdio directly.
+///
+/// Abstracting I/O behind a trait lets the VM run headless: a solver can feed a
+/// full solution script through [`BufferedIo`] and read back the captured
+/// output without ever blocking on real stdin.
+pub trait Io: core::fmt::Debug {
+    /// Read the next input byte, widened to the VM's word type.
+    fn read_byte(&mut self) -> Result<u16>;
+    /// Whether a subsequent [`read_byte`](Io::read_byte) can make progress
+    /// without stalling. Transports that block until input arrives (such as a
+    /// terminal) report `true`; a drained buffer reports `false` so the VM can
+    /// surface [`StepResult::NeedInput`] instead of erroring.
+    fn has_input(&self) -> bool {
+        true
+    }
+    /// Write a single output byte.
+    fn write_byte(&mut self, byte: u16);
+    /// Everything written so far, for inspection by embedding callers.
+    fn output(&self) -> &[u8];
+    /// Input bytes already buffered by the transport but not yet consumed, so a
+    /// snapshot can preserve input read mid-line. Transports with no such buffer
+    /// return an empty slice.
+    fn pending_input(&self) -> &[u8] {
+        &[]
+    }
+    /// Restore the buffered input captured by [`pending_input`](Io::pending_input).
+    /// Transports without a buffer ignore it.
+    fn set_pending_input(&mut self, _input: &[u8]) {}
+}
 
-        trace!(
-            "Opcode {}, instruction {:?}, length {}",
-            opcode,
-            instruction,
-            idx - index
-        );
+/// Default transport: line-buffered terminal stdin and stdout, keeping a copy
+/// of everything written. Only available with the `std` feature.
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+pub struct TerminalIo {
+    stdin: Vec<u8>,
+    stdout: Vec<u8>,
+}
 
-        Ok((instruction, idx - index))
+#[cfg(feature = "std")]
+impl TerminalIo {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Io for TerminalIo {
+    fn read_byte(&mut self) -> Result<u16> {
+        if self.stdin.is_empty() {
+            let mut input = String::new();
+            std::io::stdin().read_line(&mut input)?;
+            self.stdin.extend_from_slice(input.as_bytes());
+        }
+        Ok(self.stdin.remove(0) as u16)
+    }
+
+    fn write_byte(&mut self, byte: u16) {
+        self.stdout.push(byte as u8);
+        print!("{}", std::str::from_utf8(&[byte as u8]).unwrap());
+    }
+
+    fn output(&self) -> &[u8] {
+        &self.stdout
+    }
+
+    fn pending_input(&self) -> &[u8] {
+        &self.stdin
+    }
+
+    fn set_pending_input(&mut self, input: &[u8]) {
+        self.stdin = input.to_vec();
+    }
+}
+
+/// Programmatic transport backed by a pre-supplied input buffer and a captured
+/// output buffer, for tests and solver tools that run many inputs headless.
+#[derive(Debug, Default)]
+pub struct BufferedIo {
+    input: Vec<u8>,
+    pos: usize,
+    output: Vec<u8>,
+}
+
+impl BufferedIo {
+    pub fn new(input: Vec<u8>) -> Self {
+        Self {
+            input,
+            pos: 0,
+            output: Vec::new(),
+        }
+    }
+}
+
+impl Io for BufferedIo {
+    fn read_byte(&mut self) -> Result<u16> {
+        match self.input.get(self.pos) {
+            Some(byte) => {
+                self.pos += 1;
+                Ok(*byte as u16)
+            }
+            None => Err(VmError::InputExhausted),
+        }
+    }
+
+    fn has_input(&self) -> bool {
+        self.pos < self.input.len()
+    }
+
+    fn write_byte(&mut self, byte: u16) {
+        self.output.push(byte as u8);
+    }
+
+    fn output(&self) -> &[u8] {
+        &self.output
+    }
+
+    fn pending_input(&self) -> &[u8] {
+        &self.input[self.pos..]
+    }
+
+    fn set_pending_input(&mut self, input: &[u8]) {
+        self.input = input.to_vec();
+        self.pos = 0;
     }
 }
 
@@ -184,30 +437,135 @@ pub struct VirtualMachine {
     memory: Vec<u16>,
     registers: Vec<u16>,
     stack: Vec<u16>,
-    stdin: Vec<u8>,
-    stdout: Vec<u8>,
+    io: Box<dyn Io>,
     pc: usize,
 }
 
 impl VirtualMachine {
+    /// Construct a VM driven by the terminal transport. Only available with the
+    /// `std` feature; `no_std` embedders supply their own [`Io`] via
+    /// [`with_io`](VirtualMachine::with_io).
+    #[cfg(feature = "std")]
     pub fn new(data: Vec<u16>) -> Self {
+        Self::with_io(data, Box::new(TerminalIo::new()))
+    }
+
+    /// Construct a VM driven by a caller-supplied [`Io`] transport.
+    pub fn with_io(data: Vec<u16>, io: Box<dyn Io>) -> Self {
         Self {
             memory: data,
             registers: vec![0; 8],
             stack: Vec::new(),
-            stdin: Vec::new(),
-            stdout: Vec::new(),
+            io,
             pc: 0,
         }
     }
 
     fn out(&mut self, val: u16) {
-        self.stdout.push(val as u8);
-        print!("{}", std::str::from_utf8(&[val as u8]).unwrap());
+        self.io.write_byte(val);
     }
 
     pub fn stdout(&self) -> Vec<u8> {
-        self.stdout.clone()
+        self.io.output().to_vec()
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    pub fn set_pc(&mut self, pc: usize) {
+        self.pc = pc;
+    }
+
+    pub fn registers(&self) -> &[u16] {
+        &self.registers
+    }
+
+    /// Set register `idx`. Out-of-range indices are ignored rather than
+    /// panicking, so a mistyped `set r8 1` in the debugger cannot abort the
+    /// session.
+    pub fn set_register(&mut self, idx: usize, val: u16) {
+        if let Some(reg) = self.registers.get_mut(idx) {
+            *reg = val;
+        }
+    }
+
+    pub fn stack(&self) -> &[u16] {
+        &self.stack
+    }
+
+    pub fn memory(&self) -> &[u16] {
+        &self.memory
+    }
+
+    pub fn poke(&mut self, addr: usize, val: u16) {
+        self.memory[addr] = val;
+    }
+
+    /// Serialize the complete machine state to a compact, self-describing
+    /// binary file. The header is `SNAP` + a version byte so future fields can
+    /// be appended without breaking old saves. The transport's pending input is
+    /// captured too, so snapshotting mid-line does not lose buffered keystrokes.
+    #[cfg(feature = "std")]
+    pub fn save<P: AsRef<std::path::Path>>(&self, path: P) -> Result<()> {
+        let mut buf: Vec<u8> = Vec::new();
+
+        buf.extend_from_slice(SNAPSHOT_MAGIC);
+        buf.push(SNAPSHOT_VERSION);
+        buf.extend_from_slice(&(self.pc as u64).to_le_bytes());
+
+        write_words(&mut buf, &self.memory);
+        write_words(&mut buf, &self.registers);
+        write_words(&mut buf, &self.stack);
+        write_bytes(&mut buf, self.io.pending_input());
+
+        std::fs::write(path, buf)?;
+        Ok(())
+    }
+
+    /// Reconstruct a machine from a snapshot written by [`save`]. A truncated
+    /// or malformed file fails cleanly with a [`VmError`] rather than panicking
+    /// on a bad slice index.
+    ///
+    /// [`save`]: VirtualMachine::save
+    #[cfg(feature = "std")]
+    pub fn load<P: AsRef<std::path::Path>>(path: P) -> Result<VirtualMachine> {
+        let buf = std::fs::read(path)?;
+        let mut cur = Cursor::new(&buf);
+
+        if cur.take(SNAPSHOT_MAGIC.len())? != SNAPSHOT_MAGIC {
+            return Err(VmError::BadSnapshot("not a snapshot file: bad magic".to_string()));
+        }
+
+        let version = cur.take(1)?[0];
+        if version == 0 || version > SNAPSHOT_VERSION {
+            return Err(VmError::BadSnapshot(format!(
+                "unsupported snapshot version: {}",
+                version
+            )));
+        }
+
+        let pc = u64::from_le_bytes(cur.take(8)?.try_into().unwrap()) as usize;
+        let memory = read_words(&mut cur)?;
+        let registers = read_words(&mut cur)?;
+        let stack = read_words(&mut cur)?;
+        // Version 1 saves end after the stack and carry no pending input.
+        let pending = if version >= 2 {
+            read_bytes(&mut cur)?
+        } else {
+            Vec::new()
+        };
+
+        let mut io = TerminalIo::new();
+        io.set_pending_input(&pending);
+
+        Ok(Self {
+            memory,
+            registers,
+            stack,
+            io: Box::new(io),
+            pc,
+        })
     }
 
     fn get(&self, addr: Address) -> u16 {
@@ -220,9 +578,7 @@ impl VirtualMachine {
     fn getreg(&self, addr: Address) -> Result<usize> {
         let idx = match addr {
             Address::Register(idx) => idx as usize,
-            _ => {
-                bail!("Invalid register: {:?}", addr);
-            }
+            _ => return Err(VmError::InvalidRegister),
         };
 
         Ok(idx)
@@ -231,11 +587,13 @@ impl VirtualMachine {
     fn set(&mut self, addr: Address, val: u16) {
         match addr {
             Address::Register(idx) => {
+                #[cfg(feature = "std")]
                 trace!("REG {} <- {}", idx, val);
                 self.registers[idx as usize] = val;
             }
 
             Address::Literal(mem) => {
+                #[cfg(feature = "std")]
                 trace!("MEM {} <- {}", mem, val);
                 self.memory[mem as usize] = val;
             }
@@ -243,153 +601,164 @@ impl VirtualMachine {
     }
 
     pub fn run(&mut self) -> Result<()> {
-        loop {
-            let (instruction, length) = Instruction::decoder(&self.memory, self.pc)?;
-            self.pc += length;
-
-            match instruction {
-                Instruction::Halt => {
-                    break;
-                }
+        // Stops on `halt` and also when the transport stalls for input
+        // ([`StepResult::NeedInput`]); both leave the `while let` via a
+        // non-`Continue` result.
+        while let StepResult::Continue = self.step()? {}
+        Ok(())
+    }
 
-                Instruction::Set { op1, op2 } => {
-                    let idx = self.getreg(op1)?;
-                    let val = self.get(op2);
-                    self.registers[idx] = val;
-                }
+    /// Decode and execute exactly one instruction, advancing the program
+    /// counter. Returns [`StepResult::Halted`] when the instruction was a
+    /// `halt`, [`StepResult::NeedInput`] when an `in` could not be served
+    /// without stalling (leaving `pc` on the `in`), otherwise
+    /// [`StepResult::Continue`].
+    pub fn step(&mut self) -> Result<StepResult> {
+        let (instruction, length) = Instruction::decoder(&self.memory, self.pc)?;
+        self.pc += length;
+
+        match instruction {
+            Instruction::Halt => {
+                return Ok(StepResult::Halted);
+            }
 
-                Instruction::Push { op1 } => {
-                    let val = self.get(op1);
-                    self.stack.push(val);
-                }
+            Instruction::Set { op1, op2 } => {
+                let idx = self.getreg(op1)?;
+                let val = self.get(op2);
+                self.registers[idx] = val;
+            }
 
-                Instruction::Pop { op1 } => {
-                    if let Some(val) = self.stack.pop() {
-                        self.set(op1, val);
-                    } else {
-                        bail!("Invalid stack pop");
-                    }
-                }
+            Instruction::Push { op1 } => {
+                let val = self.get(op1);
+                self.stack.push(val);
+            }
 
-                Instruction::Eq { op1, op2, op3 } => {
-                    let val1 = self.get(op2);
-                    let val2 = self.get(op3);
-                    if val1 == val2 {
-                        self.set(op1, 1);
-                    } else {
-                        self.set(op1, 0);
-                    }
+            Instruction::Pop { op1 } => {
+                if let Some(val) = self.stack.pop() {
+                    self.set(op1, val);
+                } else {
+                    return Err(VmError::StackUnderflow);
                 }
+            }
 
-                Instruction::Gt { op1, op2, op3 } => {
-                    let val1 = self.get(op2);
-                    let val2 = self.get(op3);
-                    if val1 > val2 {
-                        self.set(op1, 1);
-                    } else {
-                        self.set(op1, 0);
-                    }
+            Instruction::Eq { op1, op2, op3 } => {
+                let val1 = self.get(op2);
+                let val2 = self.get(op3);
+                if val1 == val2 {
+                    self.set(op1, 1);
+                } else {
+                    self.set(op1, 0);
                 }
+            }
 
-                Instruction::Jmp { op1 } => {
-                    let addr = self.get(op1);
-                    self.pc = addr as usize;
+            Instruction::Gt { op1, op2, op3 } => {
+                let val1 = self.get(op2);
+                let val2 = self.get(op3);
+                if val1 > val2 {
+                    self.set(op1, 1);
+                } else {
+                    self.set(op1, 0);
                 }
+            }
 
-                Instruction::Jt { op1, op2 } => {
-                    let val = self.get(op1);
-                    let addr = self.get(op2);
-                    if val != 0 {
-                        self.pc = addr as usize;
-                    }
-                }
+            Instruction::Jmp { op1 } => {
+                let addr = self.get(op1);
+                self.pc = addr as usize;
+            }
 
-                Instruction::Jf { op1, op2 } => {
-                    let val = self.get(op1);
-                    let addr = self.get(op2);
-                    if val == 0 {
-                        self.pc = addr as usize;
-                    }
+            Instruction::Jt { op1, op2 } => {
+                let val = self.get(op1);
+                let addr = self.get(op2);
+                if val != 0 {
+                    self.pc = addr as usize;
                 }
+            }
 
-                Instruction::Add { op1, op2, op3 } => {
-                    let val1 = self.get(op2);
-                    let val2 = self.get(op3);
-                    self.set(op1, (val1 + val2) & 0x7fff);
+            Instruction::Jf { op1, op2 } => {
+                let val = self.get(op1);
+                let addr = self.get(op2);
+                if val == 0 {
+                    self.pc = addr as usize;
                 }
+            }
 
-                Instruction::Mult { op1, op2, op3 } => {
-                    let val1 = self.get(op2) as u32;
-                    let val2 = self.get(op3) as u32;
-                    let product: u32 = (val1 * val2) & 0x7fff;
-                    self.set(op1, product as u16);
-                }
+            Instruction::Add { op1, op2, op3 } => {
+                let val1 = self.get(op2);
+                let val2 = self.get(op3);
+                self.set(op1, (val1 + val2) & 0x7fff);
+            }
 
-                Instruction::Mod { op1, op2, op3 } => {
-                    let val1 = self.get(op2);
-                    let val2 = self.get(op3);
-                    self.set(op1, val1 % val2);
-                }
+            Instruction::Mult { op1, op2, op3 } => {
+                let val1 = self.get(op2) as u32;
+                let val2 = self.get(op3) as u32;
+                let product: u32 = (val1 * val2) & 0x7fff;
+                self.set(op1, product as u16);
+            }
 
-                Instruction::And { op1, op2, op3 } => {
-                    let val1 = self.get(op2);
-                    let val2 = self.get(op3);
-                    self.set(op1, val1 & val2);
-                }
+            Instruction::Mod { op1, op2, op3 } => {
+                let val1 = self.get(op2);
+                let val2 = self.get(op3);
+                self.set(op1, val1 % val2);
+            }
 
-                Instruction::Or { op1, op2, op3 } => {
-                    let val1 = self.get(op2);
-                    let val2 = self.get(op3);
-                    self.set(op1, val1 | val2);
-                }
+            Instruction::And { op1, op2, op3 } => {
+                let val1 = self.get(op2);
+                let val2 = self.get(op3);
+                self.set(op1, val1 & val2);
+            }
 
-                Instruction::Not { op1, op2 } => {
-                    let val1 = self.get(op2);
-                    self.set(op1, (!val1) & 0x7fff);
-                }
+            Instruction::Or { op1, op2, op3 } => {
+                let val1 = self.get(op2);
+                let val2 = self.get(op3);
+                self.set(op1, val1 | val2);
+            }
 
-                Instruction::Rmem { op1, op2 } => {
-                    let addr = self.get(op2);
-                    self.set(op1, self.memory[addr as usize]);
-                }
+            Instruction::Not { op1, op2 } => {
+                let val1 = self.get(op2);
+                self.set(op1, (!val1) & 0x7fff);
+            }
 
-                Instruction::Wmem { op1, op2 } => {
-                    let addr = self.get(op1);
-                    let val = self.get(op2);
-                    self.memory[addr as usize] = val;
-                }
+            Instruction::Rmem { op1, op2 } => {
+                let addr = self.get(op2);
+                self.set(op1, self.memory[addr as usize]);
+            }
 
-                Instruction::Call { op1 } => {
-                    let addr = self.get(op1);
-                    self.stack.push(self.pc as u16);
-                    self.pc = addr as usize;
-                }
+            Instruction::Wmem { op1, op2 } => {
+                let addr = self.get(op1);
+                let val = self.get(op2);
+                self.memory[addr as usize] = val;
+            }
 
-                Instruction::Ret => {
-                    let addr = self.stack.pop().unwrap();
-                    self.pc = addr as usize;
-                }
+            Instruction::Call { op1 } => {
+                let addr = self.get(op1);
+                self.stack.push(self.pc as u16);
+                self.pc = addr as usize;
+            }
 
-                Instruction::Out { op1 } => {
-                    let val = self.get(op1);
-                    self.out(val);
-                }
+            Instruction::Ret => {
+                let addr = self.stack.pop().unwrap();
+                self.pc = addr as usize;
+            }
 
-                Instruction::In { op1 } => {
-                    if self.stdin.len() == 0 {
-                        let mut input = String::new();
-                        std::io::stdin().read_line(&mut input)?;
-                        self.stdin.extend_from_slice(&input.as_bytes());
-                    }
+            Instruction::Out { op1 } => {
+                let val = self.get(op1);
+                self.out(val);
+            }
 
-                    let val = self.stdin.remove(0) as u16;
-                    self.set(op1, val);
+            Instruction::In { op1 } => {
+                if !self.io.has_input() {
+                    // Rewind to the `in` so the next step retries once the
+                    // caller supplies input.
+                    self.pc -= length;
+                    return Ok(StepResult::NeedInput);
                 }
-
-                Instruction::Noop => {}
+                let val = self.io.read_byte()?;
+                self.set(op1, val);
             }
+
+            Instruction::Noop => {}
         }
 
-        Ok(())
+        Ok(StepResult::Continue)
     }
 }